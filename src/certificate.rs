@@ -0,0 +1,224 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! X.509 bridge for RGP verifying keys: lets a bare ed25519 [`VerifyingKey`] from
+//! [`crate::generate_fingerprint`] be distributed and bound to an identity via a
+//! self-signed DER-encoded certificate, and lets a certificate be turned back into a
+//! verifying key [`crate::verify`] can use, after validating it.
+
+use crate::Fingerprint;
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::time::SystemTime;
+use x509_cert::{
+    certificate::{Certificate, TbsCertificate, Version},
+    der::{asn1::BitString, Decode, Encode},
+    ext::{
+        pkix::{constraints::BasicConstraints, KeyUsage, KeyUsages},
+        AsExtension, Extensions,
+    },
+    name::Name,
+    serial_number::SerialNumber,
+    spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+    time::{Time, Validity},
+};
+
+/// Errors returned by [`verifying_key_from_certificate`].
+#[derive(Debug)]
+pub enum CertificateError {
+    Malformed,
+    InvalidSignature,
+    Expired,
+    NotYetValid,
+    KeyUsage,
+}
+
+const ED25519_OID: &str = "1.3.101.112";
+
+fn ed25519_algorithm_identifier() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ED25519_OID.parse().expect("fixed OID string is valid"),
+        parameters: None,
+    }
+}
+
+/// Builds a self-signed, DER-encoded X.509 v3 certificate for `fingerprint`'s verifying key,
+/// with `subject` as the distinguished name and `validity` as the `(not_before, not_after)`
+/// window, setting the digital-signature key-usage bit and `CA: false` basic constraints.
+///
+/// Built by hand rather than through [`x509_cert::builder::Builder`], since that trait
+/// requires the signature type to implement `SignatureBitStringEncoding`, which
+/// `ed25519_dalek::Signature` doesn't.
+pub fn export_certificate(
+    fingerprint: &Fingerprint,
+    subject: &str,
+    validity: (time::OffsetDateTime, time::OffsetDateTime),
+) -> Result<Vec<u8>, CertificateError> {
+    let verifying_key = fingerprint.verifying_key();
+
+    let subject_name: Name = subject.parse().map_err(|_| CertificateError::Malformed)?;
+
+    let spki = SubjectPublicKeyInfoOwned {
+        algorithm: ed25519_algorithm_identifier(),
+        subject_public_key: BitString::from_bytes(verifying_key.as_bytes())
+            .map_err(|_| CertificateError::Malformed)?,
+    };
+
+    let not_before = Time::try_from(SystemTime::from(validity.0))
+        .map_err(|_| CertificateError::Malformed)?;
+    let not_after = Time::try_from(SystemTime::from(validity.1))
+        .map_err(|_| CertificateError::Malformed)?;
+
+    let key_usage = KeyUsage(KeyUsages::DigitalSignature.into())
+        .to_extension(&subject_name, &[])
+        .map_err(|_| CertificateError::Malformed)?;
+    let basic_constraints = BasicConstraints {
+        ca: false,
+        path_len_constraint: None,
+    }
+    .to_extension(&subject_name, std::slice::from_ref(&key_usage))
+    .map_err(|_| CertificateError::Malformed)?;
+
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: SerialNumber::from(1u32),
+        signature: ed25519_algorithm_identifier(),
+        issuer: subject_name.clone(),
+        validity: Validity {
+            not_before,
+            not_after,
+        },
+        subject: subject_name,
+        subject_public_key_info: spki,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(vec![key_usage, basic_constraints]),
+    };
+
+    let tbs_der = tbs_certificate
+        .to_der()
+        .map_err(|_| CertificateError::Malformed)?;
+    let signature = crate::sign(fingerprint, &tbs_der);
+
+    let certificate = Certificate {
+        tbs_certificate,
+        signature_algorithm: ed25519_algorithm_identifier(),
+        signature: BitString::from_bytes(&signature.to_bytes())
+            .map_err(|_| CertificateError::Malformed)?,
+    };
+
+    certificate
+        .to_der()
+        .map_err(|_| CertificateError::Malformed)
+}
+
+const KEY_USAGE_OID: &str = "2.5.29.15";
+
+fn key_usage_allows_digital_signature(extensions: &Extensions) -> Result<bool, CertificateError> {
+    let Some(extension) = extensions
+        .iter()
+        .find(|ext| ext.extn_id.to_string() == KEY_USAGE_OID)
+    else {
+        return Ok(true);
+    };
+
+    let bit_string =
+        BitString::from_der(extension.extn_value.as_bytes()).map_err(|_| CertificateError::Malformed)?;
+
+    Ok(bit_string
+        .raw_bytes()
+        .first()
+        .map(|byte| byte & 0b1000_0000 != 0)
+        .unwrap_or(false))
+}
+
+/// Parses a DER-encoded certificate produced by [`export_certificate`] (or an equivalent
+/// self-signed Ed25519 certificate), validates its self-signature, validity window, and
+/// digital-signature key-usage bit, then returns the verifying key for use with
+/// [`crate::verify`].
+pub fn verifying_key_from_certificate(der: &[u8]) -> Result<VerifyingKey, CertificateError> {
+    let certificate = Certificate::from_der(der).map_err(|_| CertificateError::Malformed)?;
+
+    let spki = &certificate.tbs_certificate.subject_public_key_info;
+    let key_bytes: [u8; 32] = spki
+        .subject_public_key
+        .as_bytes()
+        .ok_or(CertificateError::Malformed)?
+        .try_into()
+        .map_err(|_| CertificateError::Malformed)?;
+
+    let verifying_key =
+        VerifyingKey::from_bytes(&key_bytes).map_err(|_| CertificateError::Malformed)?;
+
+    let now = SystemTime::now();
+    let not_before = SystemTime::from(certificate.tbs_certificate.validity.not_before);
+    let not_after = SystemTime::from(certificate.tbs_certificate.validity.not_after);
+
+    if now < not_before {
+        return Err(CertificateError::NotYetValid);
+    }
+    if now > not_after {
+        return Err(CertificateError::Expired);
+    }
+
+    if let Some(extensions) = &certificate.tbs_certificate.extensions {
+        if !key_usage_allows_digital_signature(extensions)? {
+            return Err(CertificateError::KeyUsage);
+        }
+    }
+
+    let tbs_der = certificate
+        .tbs_certificate
+        .to_der()
+        .map_err(|_| CertificateError::Malformed)?;
+
+    let signature_bytes: [u8; 64] = certificate
+        .signature
+        .as_bytes()
+        .ok_or(CertificateError::Malformed)?
+        .try_into()
+        .map_err(|_| CertificateError::Malformed)?;
+
+    let signature = Signature::from_bytes(&signature_bytes);
+
+    crate::verify(&signature, &verifying_key, &tbs_der)
+        .map(|_| verifying_key)
+        .map_err(|_| CertificateError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn certificate_round_trips_and_validates() {
+        let (fingerprint, verifying_key) = crate::generate_fingerprint();
+
+        let not_before = time::OffsetDateTime::now_utc() - time::Duration::minutes(5);
+        let not_after = time::OffsetDateTime::now_utc() + time::Duration::days(365);
+
+        let der = export_certificate(&fingerprint, "CN=rgp test", (not_before, not_after)).unwrap();
+
+        let recovered_verifying_key = verifying_key_from_certificate(&der).unwrap();
+
+        assert_eq!(recovered_verifying_key, verifying_key);
+    }
+
+    #[test]
+    fn expired_certificate_is_rejected() {
+        let (fingerprint, _) = crate::generate_fingerprint();
+
+        let not_before = time::OffsetDateTime::now_utc() - time::Duration::days(2);
+        let not_after = time::OffsetDateTime::now_utc() - time::Duration::days(1);
+
+        let der = export_certificate(&fingerprint, "CN=rgp test", (not_before, not_after)).unwrap();
+
+        assert!(matches!(
+            verifying_key_from_certificate(&der),
+            Err(CertificateError::Expired)
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use crate::encrypt::WRAPPED_KEY_LEN;
+use crate::password::PasswordHeader;
+
+/// The mode-specific component extracted from encrypted content by [`extract_components`]/
+/// [`extract_components_mut`].
+pub enum Components {
+    /// `Encrypt::Session` carries no per-recipient data.
+    Session,
+    /// `Encrypt::Hmac` carries no per-recipient data; the HMAC tag stays in the body for
+    /// [`crate::decrypt`] to check.
+    Hmac,
+    /// The wrapped content key for the requested recipient index, to be passed to
+    /// [`crate::Decrypt::Dh`].
+    Dh([u8; WRAPPED_KEY_LEN]),
+    /// The salt and cost parameters to pass to [`crate::Decrypt::Password`].
+    Password(PasswordHeader),
+}
+
+fn take(encrypted_content: &mut Vec<u8>, len: usize) -> Vec<u8> {
+    assert!(
+        encrypted_content.len() >= len,
+        "encrypted_content is malformed or wasn't produced by crate::encrypt"
+    );
+
+    encrypted_content.drain(..len).collect()
+}
+
+/// Strips the mode header from `encrypted_content` in place and returns the component for
+/// recipient `index`, leaving the nonce/ciphertext/signature body for [`crate::decrypt`].
+///
+/// Panics if `encrypted_content` wasn't produced by [`crate::encrypt`] or doesn't carry a
+/// wrapped key for `index`.
+pub fn extract_components_mut(index: usize, encrypted_content: &mut Vec<u8>) -> Components {
+    let tag = *encrypted_content
+        .first()
+        .expect("encrypted_content is malformed or wasn't produced by crate::encrypt");
+
+    match tag {
+        0 => {
+            take(encrypted_content, 1);
+            Components::Session
+        }
+        1 => {
+            take(encrypted_content, 1 + 4);
+            Components::Hmac
+        }
+        2 => {
+            take(encrypted_content, 1);
+
+            let count_bytes = take(encrypted_content, 2);
+            let count = u16::from_le_bytes(count_bytes.try_into().unwrap()) as usize;
+
+            let mut selected = None;
+
+            for i in 0..count {
+                let wrapped = take(encrypted_content, WRAPPED_KEY_LEN);
+
+                if i == index {
+                    selected = Some(wrapped.try_into().unwrap());
+                }
+            }
+
+            Components::Dh(selected.expect("no wrapped key for the requested recipient index"))
+        }
+        3 => {
+            let header_bytes = take(encrypted_content, 1 + crate::password::HEADER_LEN);
+            Components::Password(PasswordHeader::from_bytes(
+                header_bytes[1..].try_into().unwrap(),
+            ))
+        }
+        _ => panic!("encrypted_content is malformed or wasn't produced by crate::encrypt"),
+    }
+}
+
+/// Like [`extract_components_mut`], but takes `encrypted_content` by value so the caller
+/// doesn't have to manage the in-place mutation themselves.
+pub fn extract_components(index: usize, mut encrypted_content: Vec<u8>) -> Components {
+    extract_components_mut(index, &mut encrypted_content)
+}
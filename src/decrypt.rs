@@ -0,0 +1,245 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use crate::encrypt::WRAPPED_KEY_LEN;
+use crate::password::{derive_password_key, PasswordHeader};
+use crate::{dh::shared_secret, verify, DhKey, DhPublicKey, Error, Signature};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// How to resolve the content key for [`crate::decrypt`], mirroring [`crate::Encrypt`].
+pub enum Decrypt<'a> {
+    Session(DhKey),
+    Hmac(DhKey, DhPublicKey),
+    /// The wrapped key for this recipient (from [`crate::Components::Dh`]), the sender's
+    /// public key, and this recipient's own private key.
+    Dh([u8; WRAPPED_KEY_LEN], DhPublicKey, DhKey),
+    /// The header for this message (from [`crate::Components::Password`]) and the passphrase
+    /// to re-derive the content key with.
+    Password(PasswordHeader, &'a [u8]),
+}
+
+pub(crate) fn unwrap_key(
+    wrapped: &[u8; WRAPPED_KEY_LEN],
+    sender_pub: DhPublicKey,
+    receiver_priv: DhKey,
+) -> Result<[u8; 32], Error> {
+    let (nonce_bytes, ciphertext) = wrapped.split_at(12);
+
+    let shared = shared_secret(receiver_priv, sender_pub);
+    let hk = Hkdf::<Sha256>::new(None, &shared);
+    let mut kek = [0u8; 32];
+    hk.expand(b"RGP-Dh-wrap", &mut kek)
+        .expect("32 bytes is a valid HKDF output length for SHA-256");
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&kek));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| Error::Decrypt)?;
+
+    plaintext.try_into().map_err(|_| Error::Malformed)
+}
+
+/// Decrypts the body left by [`crate::extract_components_mut`], optionally checking an
+/// ed25519 signature over the ciphertext first.
+pub fn decrypt(
+    verifying_key: Option<&VerifyingKey>,
+    encrypted_content: &[u8],
+    mode: Decrypt,
+) -> Result<Vec<u8>, Error> {
+    let (hmac_tag, rest) = match &mode {
+        Decrypt::Hmac(_, _) => {
+            if encrypted_content.len() < 32 {
+                return Err(Error::Malformed);
+            }
+            let (tag, rest) = encrypted_content.split_at(32);
+            (Some(tag), rest)
+        }
+        _ => (None, encrypted_content),
+    };
+
+    if rest.len() < 12 + 64 {
+        return Err(Error::Malformed);
+    }
+
+    let (nonce_bytes, rest) = rest.split_at(12);
+    let (ciphertext, signature_bytes) = rest.split_at(rest.len() - 64);
+
+    if let Some(verifying_key) = verifying_key {
+        let signature = Signature::from_bytes(signature_bytes.try_into().unwrap());
+        verify(&signature, verifying_key, ciphertext)?;
+    }
+
+    let content_key = match mode {
+        Decrypt::Session(key) => key.0,
+        Decrypt::Hmac(hmac_key, key) => {
+            let tag = hmac_tag.expect("set above for Decrypt::Hmac");
+
+            let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hmac_key.0)
+                .expect("HMAC-SHA256 accepts any key length");
+            mac.update(nonce_bytes);
+            mac.update(ciphertext);
+            mac.verify_slice(tag).map_err(|_| Error::Decrypt)?;
+
+            key.0
+        }
+        Decrypt::Dh(wrapped, sender_pub, receiver_priv) => {
+            unwrap_key(&wrapped, sender_pub, receiver_priv)?
+        }
+        Decrypt::Password(header, passphrase) => {
+            derive_password_key(passphrase, &header.salt, &header.params).map_err(|_| Error::Decrypt)?
+        }
+    };
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&content_key));
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| Error::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::PasswordParams;
+    use crate::{extract_components_mut, generate_dh_keys, generate_fingerprint, Components, Encrypt};
+
+    #[test]
+    fn session_round_trips() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (key, _) = generate_dh_keys();
+        let content = b"session mode".to_vec();
+
+        let (mut encrypted, _) = crate::encrypt(fingerprint, content.clone(), Encrypt::Session(key)).unwrap();
+        assert!(matches!(
+            extract_components_mut(0, &mut encrypted),
+            Components::Session
+        ));
+
+        let decrypted = decrypt(Some(&verifying_key), &encrypted, Decrypt::Session(key)).unwrap();
+        assert_eq!(decrypted, content);
+    }
+
+    #[test]
+    fn hmac_round_trips() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (hmac_key, hmac_pub) = generate_dh_keys();
+        let content = b"hmac mode".to_vec();
+
+        let (mut encrypted, _) = crate::encrypt(
+            fingerprint,
+            content.clone(),
+            Encrypt::Hmac(hmac_key, hmac_pub, 0),
+        )
+        .unwrap();
+        assert!(matches!(extract_components_mut(0, &mut encrypted), Components::Hmac));
+
+        let decrypted = decrypt(
+            Some(&verifying_key),
+            &encrypted,
+            Decrypt::Hmac(hmac_key, hmac_pub),
+        )
+        .unwrap();
+        assert_eq!(decrypted, content);
+    }
+
+    #[test]
+    fn dh_round_trips_for_each_recipient() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (sender_priv, sender_pub) = generate_dh_keys();
+        let (receiver_a_priv, receiver_a_pub) = generate_dh_keys();
+        let (receiver_b_priv, receiver_b_pub) = generate_dh_keys();
+        let content = b"dh mode".to_vec();
+
+        let (encrypted, _) = crate::encrypt(
+            fingerprint,
+            content.clone(),
+            Encrypt::Dh(sender_priv, &[receiver_a_pub, receiver_b_pub]),
+        )
+        .unwrap();
+
+        let mut copy_a = encrypted.clone();
+        let wrapped_a = match extract_components_mut(0, &mut copy_a) {
+            Components::Dh(wrapped) => wrapped,
+            _ => panic!("expected Components::Dh"),
+        };
+        let decrypted_a = decrypt(
+            Some(&verifying_key),
+            &copy_a,
+            Decrypt::Dh(wrapped_a, sender_pub, receiver_a_priv),
+        )
+        .unwrap();
+        assert_eq!(decrypted_a, content);
+
+        let mut copy_b = encrypted;
+        let wrapped_b = match extract_components_mut(1, &mut copy_b) {
+            Components::Dh(wrapped) => wrapped,
+            _ => panic!("expected Components::Dh"),
+        };
+        let decrypted_b = decrypt(
+            Some(&verifying_key),
+            &copy_b,
+            Decrypt::Dh(wrapped_b, sender_pub, receiver_b_priv),
+        )
+        .unwrap();
+        assert_eq!(decrypted_b, content);
+    }
+
+    #[test]
+    fn password_round_trips() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let content = b"password mode".to_vec();
+        let params = PasswordParams::default();
+
+        let (mut encrypted, _) = crate::encrypt(
+            fingerprint,
+            content.clone(),
+            Encrypt::Password(b"correct horse battery staple", params),
+        )
+        .unwrap();
+
+        let header = match extract_components_mut(0, &mut encrypted) {
+            Components::Password(header) => header,
+            _ => panic!("expected Components::Password"),
+        };
+
+        let decrypted = decrypt(
+            Some(&verifying_key),
+            &encrypted,
+            Decrypt::Password(header, b"correct horse battery staple"),
+        )
+        .unwrap();
+        assert_eq!(decrypted, content);
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails_signature_check() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (key, _) = generate_dh_keys();
+        let content = b"session mode".to_vec();
+
+        let (mut encrypted, _) = crate::encrypt(fingerprint, content, Encrypt::Session(key)).unwrap();
+        assert!(matches!(
+            extract_components_mut(0, &mut encrypted),
+            Components::Session
+        ));
+
+        // The body is now nonce (12 bytes), ciphertext, signature; flip the first ciphertext byte.
+        encrypted[12] ^= 0xFF;
+
+        assert_eq!(
+            decrypt(Some(&verifying_key), &encrypted, Decrypt::Session(key)),
+            Err(Error::Verify)
+        );
+    }
+}
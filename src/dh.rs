@@ -0,0 +1,45 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use rand_core::OsRng;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// A 32-byte x25519 private key. Also used directly as raw symmetric key material by
+/// [`crate::Encrypt::Session`]/[`crate::Encrypt::Hmac`].
+#[derive(Clone, Copy)]
+pub struct DhKey(pub [u8; 32]);
+
+/// The public half of a [`DhKey`].
+#[derive(Clone, Copy)]
+pub struct DhPublicKey(pub [u8; 32]);
+
+impl From<DhKey> for StaticSecret {
+    fn from(key: DhKey) -> StaticSecret {
+        StaticSecret::from(key.0)
+    }
+}
+
+impl From<DhPublicKey> for PublicKey {
+    fn from(key: DhPublicKey) -> PublicKey {
+        PublicKey::from(key.0)
+    }
+}
+
+/// Generates a fresh, random x25519 key pair.
+pub fn generate_dh_keys() -> (DhKey, DhPublicKey) {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+
+    (DhKey(secret.to_bytes()), DhPublicKey(public.to_bytes()))
+}
+
+pub(crate) fn shared_secret(priv_key: DhKey, pub_key: DhPublicKey) -> [u8; 32] {
+    let secret = StaticSecret::from(priv_key);
+    let public = PublicKey::from(pub_key);
+
+    secret.diffie_hellman(&public).to_bytes()
+}
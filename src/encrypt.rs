@@ -0,0 +1,137 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use crate::password::{encrypt_password_setup, PasswordParams};
+use crate::{dh::shared_secret, sign, DhKey, DhPublicKey, Error, Fingerprint, Signature};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+
+/// The key-wrapping scheme used to protect a message's content key.
+pub enum Encrypt<'a> {
+    /// The content key *is* the given pre-shared key.
+    Session(DhKey),
+    /// Like [`Encrypt::Session`], plus an HMAC-SHA256 tag over the ciphertext keyed with a
+    /// second pre-shared key, checked independently of any ed25519 signature.
+    Hmac(DhKey, DhPublicKey, u32),
+    /// The content key is random and wrapped once per recipient public key via ECDH + HKDF.
+    Dh(DhKey, &'a [DhPublicKey]),
+    /// The content key is derived from `passphrase` with Argon2id under a fresh random salt,
+    /// using `params` for the cost parameters.
+    Password(&'a [u8], PasswordParams),
+}
+
+/// A wrapped content key is a fixed 12-byte nonce, the 32-byte content key, and a 16-byte
+/// Poly1305 tag, always this exact size.
+pub(crate) const WRAPPED_KEY_LEN: usize = 12 + 32 + 16;
+
+pub(crate) fn wrap_key_for_recipient(
+    sender_priv: DhKey,
+    recipient_pub: DhPublicKey,
+    content_key: &[u8; 32],
+) -> [u8; WRAPPED_KEY_LEN] {
+    let shared = shared_secret(sender_priv, recipient_pub);
+
+    let hk = Hkdf::<Sha256>::new(None, &shared);
+    let mut kek = [0u8; 32];
+    hk.expand(b"RGP-Dh-wrap", &mut kek)
+        .expect("32 bytes is a valid HKDF output length for SHA-256");
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&kek));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), content_key.as_slice())
+        .expect("chacha20poly1305 encryption is infallible for valid keys/nonces");
+
+    let mut wrapped = [0u8; WRAPPED_KEY_LEN];
+    wrapped[..12].copy_from_slice(&nonce_bytes);
+    wrapped[12..].copy_from_slice(&ciphertext);
+
+    wrapped
+}
+
+/// Encrypts `content` under `fingerprint`, wrapping the content key per `mode`.
+///
+/// Returns the encrypted content (mode header, nonce, ciphertext, and detached signature all
+/// concatenated) alongside the signature on its own.
+pub fn encrypt(fingerprint: Fingerprint, content: Vec<u8>, mode: Encrypt) -> Result<(Vec<u8>, Signature), Error> {
+    let mut password_header = None;
+
+    let content_key: [u8; 32] = match &mode {
+        Encrypt::Session(key) => key.0,
+        Encrypt::Hmac(_, key, _) => key.0,
+        Encrypt::Dh(_, _) => {
+            let mut key = [0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            key
+        }
+        Encrypt::Password(passphrase, params) => {
+            let (key, header) =
+                encrypt_password_setup(&mut OsRng, passphrase, *params).map_err(|_| Error::Encrypt)?;
+            password_header = Some(header);
+            key
+        }
+    };
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&content_key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), content.as_slice())
+        .map_err(|_| Error::Encrypt)?;
+
+    let signature = sign(&fingerprint, &ciphertext);
+
+    let mut out = Vec::new();
+
+    match mode {
+        Encrypt::Session(_) => {
+            out.push(0u8);
+        }
+        Encrypt::Hmac(hmac_key, _, counter) => {
+            out.push(1u8);
+            out.extend_from_slice(&counter.to_le_bytes());
+
+            use hmac::{Hmac, Mac};
+            let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hmac_key.0)
+                .expect("HMAC-SHA256 accepts any key length");
+            mac.update(&nonce_bytes);
+            mac.update(&ciphertext);
+            out.extend_from_slice(&mac.finalize().into_bytes());
+        }
+        Encrypt::Dh(sender_priv, recipients) => {
+            out.push(2u8);
+            out.extend_from_slice(&(recipients.len() as u16).to_le_bytes());
+
+            for recipient_pub in recipients {
+                let wrapped = wrap_key_for_recipient(sender_priv, *recipient_pub, &content_key);
+                out.extend_from_slice(&wrapped);
+            }
+        }
+        Encrypt::Password(_, _) => {
+            out.push(3u8);
+            out.extend_from_slice(
+                &password_header
+                    .expect("set above for Encrypt::Password")
+                    .to_bytes(),
+            );
+        }
+    }
+
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&signature.to_bytes());
+
+    Ok((out, signature))
+}
@@ -0,0 +1,36 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use std::fmt;
+
+/// The error type returned by this crate's fallible operations.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Content could not be encrypted.
+    Encrypt,
+    /// Content could not be decrypted (bad key, corrupt ciphertext, or a failed auth tag).
+    Decrypt,
+    /// A signature did not verify against the given content and verifying key.
+    Verify,
+    /// Encrypted content was truncated or otherwise didn't match the expected layout.
+    Malformed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Error::Encrypt => "failed to encrypt content",
+            Error::Decrypt => "failed to decrypt content",
+            Error::Verify => "signature did not verify",
+            Error::Malformed => "encrypted content is malformed",
+        };
+
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,59 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+use crate::Error;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use rand_core::OsRng;
+
+pub use ed25519_dalek::Signature;
+
+/// An ed25519 signing key. Copy, so a single fingerprint can be reused across many
+/// [`sign`] calls without the caller juggling references.
+#[derive(Clone, Copy)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Wraps a raw 32-byte ed25519 seed as a [`Fingerprint`], e.g. one derived or recovered
+    /// outside [`generate_fingerprint`] (see [`crate::fingerprint_from_seed`]).
+    pub fn from_bytes(bytes: [u8; 32]) -> Fingerprint {
+        Fingerprint(bytes)
+    }
+
+    /// The verifying key corresponding to this fingerprint.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key().verifying_key()
+    }
+
+    fn signing_key(&self) -> SigningKey {
+        SigningKey::from_bytes(&self.0)
+    }
+}
+
+/// Generates a fresh, random signing fingerprint and its verifying key.
+pub fn generate_fingerprint() -> (Fingerprint, VerifyingKey) {
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let fingerprint = Fingerprint(signing_key.to_bytes());
+    let verifying_key = signing_key.verifying_key();
+
+    (fingerprint, verifying_key)
+}
+
+/// Signs `content` with `fingerprint`.
+pub fn sign(fingerprint: &Fingerprint, content: &[u8]) -> Signature {
+    fingerprint.signing_key().sign(content)
+}
+
+/// Verifies that `signature` was produced over `content` by the holder of `verifying_key`.
+pub fn verify(
+    signature: &Signature,
+    verifying_key: &VerifyingKey,
+    content: &[u8],
+) -> Result<(), Error> {
+    verifying_key
+        .verify_strict(content, signature)
+        .map_err(|_| Error::Verify)
+}
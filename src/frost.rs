@@ -0,0 +1,380 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! `t`-of-`n` threshold signing (FROST) over the same ed25519 scalar field used by
+//! [`crate::generate_fingerprint`]/[`crate::sign`]/[`crate::verify`]. The reconstructed group
+//! verifying key is the constant-term commitment of the sharing polynomial, so signatures
+//! produced here verify against `rgp::verify` unchanged.
+
+use crate::Error;
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE, edwards::CompressedEdwardsY, edwards::EdwardsPoint,
+    scalar::Scalar,
+};
+use ed25519_dalek::{Signature, VerifyingKey};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Scalar, Error> {
+    Option::from(Scalar::from_canonical_bytes(*bytes)).ok_or(Error::Malformed)
+}
+
+fn point_from_bytes(bytes: &[u8; 32]) -> Result<EdwardsPoint, Error> {
+    CompressedEdwardsY(*bytes).decompress().ok_or(Error::Malformed)
+}
+
+/// Errors returned by [`generate_threshold_fingerprint`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ThresholdError {
+    /// `t` was zero; no threshold can be met with an empty sharing polynomial.
+    ZeroThreshold,
+    /// `t` was greater than `n`; the shares could never reach the threshold to reconstruct
+    /// a signature.
+    ThresholdExceedsParticipants,
+}
+
+/// Serialized size of a [`Share`].
+pub const SHARE_LEN: usize = 2 + 32;
+
+/// One participant's share of a threshold fingerprint, produced by
+/// [`generate_threshold_fingerprint`]. Kept secret by its holder — never published.
+#[derive(Clone)]
+pub struct Share {
+    pub index: u16,
+    secret: Scalar,
+}
+
+impl Share {
+    /// Serializes this share to bytes, for a signer to persist or transport to itself across
+    /// processes (e.g. into the separate process that will run [`round_one`]/[`round_two`]).
+    pub fn to_bytes(&self) -> [u8; SHARE_LEN] {
+        let mut bytes = [0u8; SHARE_LEN];
+        bytes[..2].copy_from_slice(&self.index.to_le_bytes());
+        bytes[2..].copy_from_slice(self.secret.as_bytes());
+        bytes
+    }
+
+    /// Deserializes a [`Share`] produced by [`Share::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; SHARE_LEN]) -> Result<Share, Error> {
+        let index = u16::from_le_bytes(bytes[..2].try_into().unwrap());
+        let secret = scalar_from_bytes(bytes[2..].try_into().unwrap())?;
+
+        Ok(Share { index, secret })
+    }
+}
+
+/// The pair of per-signing-round nonces a participant must keep secret between
+/// [`round_one`] and [`round_two`].
+pub struct NoncePair {
+    hiding: Scalar,
+    binding: Scalar,
+}
+
+/// Serialized size of a [`Commitment`].
+pub const COMMITMENT_LEN: usize = 2 + 32 + 32;
+
+/// The public commitment a participant publishes at the end of [`round_one`].
+#[derive(Clone)]
+pub struct Commitment {
+    pub index: u16,
+    hiding: EdwardsPoint,
+    binding: EdwardsPoint,
+}
+
+impl Commitment {
+    /// Serializes this commitment to bytes, to publish to the other signers in the round.
+    pub fn to_bytes(&self) -> [u8; COMMITMENT_LEN] {
+        let mut bytes = [0u8; COMMITMENT_LEN];
+        bytes[..2].copy_from_slice(&self.index.to_le_bytes());
+        bytes[2..34].copy_from_slice(self.hiding.compress().as_bytes());
+        bytes[34..].copy_from_slice(self.binding.compress().as_bytes());
+        bytes
+    }
+
+    /// Deserializes a [`Commitment`] published by another signer via [`Commitment::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; COMMITMENT_LEN]) -> Result<Commitment, Error> {
+        let index = u16::from_le_bytes(bytes[..2].try_into().unwrap());
+        let hiding = point_from_bytes(bytes[2..34].try_into().unwrap())?;
+        let binding = point_from_bytes(bytes[34..].try_into().unwrap())?;
+
+        Ok(Commitment {
+            index,
+            hiding,
+            binding,
+        })
+    }
+}
+
+/// Serialized size of a [`SignatureShare`].
+pub const SIGNATURE_SHARE_LEN: usize = 2 + 32;
+
+/// A single signer's contribution to the aggregate signature, produced by [`round_two`].
+pub struct SignatureShare {
+    pub index: u16,
+    z: Scalar,
+}
+
+impl SignatureShare {
+    /// Serializes this signature share to bytes, to send to whoever calls [`aggregate`].
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SHARE_LEN] {
+        let mut bytes = [0u8; SIGNATURE_SHARE_LEN];
+        bytes[..2].copy_from_slice(&self.index.to_le_bytes());
+        bytes[2..].copy_from_slice(self.z.as_bytes());
+        bytes
+    }
+
+    /// Deserializes a [`SignatureShare`] produced by [`SignatureShare::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_SHARE_LEN]) -> Result<SignatureShare, Error> {
+        let index = u16::from_le_bytes(bytes[..2].try_into().unwrap());
+        let z = scalar_from_bytes(bytes[2..].try_into().unwrap())?;
+
+        Ok(SignatureShare { index, z })
+    }
+}
+
+fn eval_poly(coefficients: &[Scalar], x: Scalar) -> Scalar {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+/// Shamir-secret-shares a fresh signing scalar over the ed25519 scalar field with a
+/// degree-`t - 1` polynomial, returning one [`Share`] per participant in `1..=n` and the
+/// verifying key for the constant term (the reconstructed group secret).
+pub fn generate_threshold_fingerprint(
+    t: u16,
+    n: u16,
+) -> Result<(Vec<Share>, VerifyingKey), ThresholdError> {
+    if t == 0 {
+        return Err(ThresholdError::ZeroThreshold);
+    }
+    if t > n {
+        return Err(ThresholdError::ThresholdExceedsParticipants);
+    }
+
+    let mut rng = rand_core::OsRng;
+
+    let coefficients: Vec<Scalar> = (0..t)
+        .map(|_| {
+            let mut bytes = [0u8; 64];
+            rng.fill_bytes(&mut bytes);
+            Scalar::from_bytes_mod_order_wide(&bytes)
+        })
+        .collect();
+
+    let shares = (1..=n)
+        .map(|index| Share {
+            index,
+            secret: eval_poly(&coefficients, Scalar::from(index as u64)),
+        })
+        .collect();
+
+    let group_point = &coefficients[0] * ED25519_BASEPOINT_TABLE;
+    let group_vk = VerifyingKey::from_bytes(group_point.compress().as_bytes())
+        .expect("compressed edwards point is always a valid verifying key");
+
+    Ok((shares, group_vk))
+}
+
+/// Round one: sample this signer's hiding/binding nonces and publish their commitment.
+pub fn round_one<R: RngCore + CryptoRng>(rng: &mut R, share: &Share) -> (NoncePair, Commitment) {
+    let mut hiding_bytes = [0u8; 64];
+    let mut binding_bytes = [0u8; 64];
+    rng.fill_bytes(&mut hiding_bytes);
+    rng.fill_bytes(&mut binding_bytes);
+
+    let hiding = Scalar::from_bytes_mod_order_wide(&hiding_bytes);
+    let binding = Scalar::from_bytes_mod_order_wide(&binding_bytes);
+
+    let commitment = Commitment {
+        index: share.index,
+        hiding: &hiding * ED25519_BASEPOINT_TABLE,
+        binding: &binding * ED25519_BASEPOINT_TABLE,
+    };
+
+    (NoncePair { hiding, binding }, commitment)
+}
+
+fn binding_factor(index: u16, msg: &[u8], commitments: &[Commitment]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(b"RGP-FROST-rho");
+    hasher.update(index.to_le_bytes());
+    hasher.update(msg);
+
+    for commitment in commitments {
+        hasher.update(commitment.index.to_le_bytes());
+        hasher.update(commitment.hiding.compress().as_bytes());
+        hasher.update(commitment.binding.compress().as_bytes());
+    }
+
+    Scalar::from_hash(hasher)
+}
+
+fn group_commitment(msg: &[u8], commitments: &[Commitment]) -> EdwardsPoint {
+    commitments
+        .iter()
+        .map(|commitment| {
+            commitment.hiding + binding_factor(commitment.index, msg, commitments) * commitment.binding
+        })
+        .sum()
+}
+
+fn challenge(r: &EdwardsPoint, group_vk: &VerifyingKey, msg: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(r.compress().as_bytes());
+    hasher.update(group_vk.as_bytes());
+    hasher.update(msg);
+
+    Scalar::from_hash(hasher)
+}
+
+fn lagrange_coefficient(index: u16, signer_indices: &[u16]) -> Scalar {
+    let x_i = Scalar::from(index as u64);
+
+    signer_indices
+        .iter()
+        .filter(|&&j| j != index)
+        .map(|&j| {
+            let x_j = Scalar::from(j as u64);
+            x_j * (x_j - x_i).invert()
+        })
+        .product()
+}
+
+/// Round two: recompute the group commitment and per-signer binding factors, then produce
+/// this signer's signature share `z_i = d_i + ρ_i·e_i + λ_i·c·s_i`.
+///
+/// `commitments` must contain the published [`Commitment`] from every active signer
+/// (including this one), in the same order on every participant.
+pub fn round_two(
+    share: &Share,
+    nonce_pair: &NoncePair,
+    msg: &[u8],
+    commitments: &[Commitment],
+    group_vk: &VerifyingKey,
+) -> SignatureShare {
+    let signer_indices: Vec<u16> = commitments.iter().map(|c| c.index).collect();
+
+    let rho_i = binding_factor(share.index, msg, commitments);
+    let r = group_commitment(msg, commitments);
+    let c = challenge(&r, group_vk, msg);
+    let lambda_i = lagrange_coefficient(share.index, &signer_indices);
+
+    let z = nonce_pair.hiding + rho_i * nonce_pair.binding + lambda_i * c * share.secret;
+
+    SignatureShare { index: share.index, z }
+}
+
+/// Sums the active signers' [`SignatureShare`]s into the final `(R, z)` ed25519 signature.
+pub fn aggregate(
+    msg: &[u8],
+    commitments: &[Commitment],
+    shares: &[SignatureShare],
+) -> Signature {
+    let r = group_commitment(msg, commitments);
+    let z: Scalar = shares.iter().map(|share| share.z).sum();
+
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(r.compress().as_bytes());
+    bytes[32..].copy_from_slice(z.as_bytes());
+
+    Signature::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn zero_threshold_is_rejected() {
+        assert!(matches!(
+            generate_threshold_fingerprint(0, 3),
+            Err(ThresholdError::ZeroThreshold)
+        ));
+    }
+
+    #[test]
+    fn threshold_exceeding_participants_is_rejected() {
+        assert!(matches!(
+            generate_threshold_fingerprint(4, 3),
+            Err(ThresholdError::ThresholdExceedsParticipants)
+        ));
+    }
+
+    #[test]
+    fn threshold_signature_verifies_against_group_key() {
+        let msg = b"threshold signatures verify like any other";
+
+        let (shares, group_vk) = generate_threshold_fingerprint(2, 3).unwrap();
+        let signers = &shares[..2];
+
+        let (nonce_pairs, commitments): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|share| round_one(&mut OsRng, share))
+            .unzip();
+
+        let signature_shares: Vec<SignatureShare> = signers
+            .iter()
+            .zip(nonce_pairs.iter())
+            .map(|(share, nonce_pair)| round_two(share, nonce_pair, msg, &commitments, &group_vk))
+            .collect();
+
+        let signature = aggregate(msg, &commitments, &signature_shares);
+
+        crate::verify(&signature, &group_vk, msg).unwrap();
+    }
+
+    #[test]
+    fn share_round_trips_through_bytes() {
+        let (shares, _) = generate_threshold_fingerprint(2, 3).unwrap();
+        let share = &shares[0];
+
+        let decoded = Share::from_bytes(&share.to_bytes()).unwrap();
+
+        assert_eq!(decoded.index, share.index);
+        assert_eq!(decoded.secret, share.secret);
+    }
+
+    #[test]
+    fn commitment_round_trips_through_bytes() {
+        let (shares, _) = generate_threshold_fingerprint(2, 3).unwrap();
+        let (_, commitment) = round_one(&mut OsRng, &shares[0]);
+
+        let decoded = Commitment::from_bytes(&commitment.to_bytes()).unwrap();
+
+        assert_eq!(decoded.index, commitment.index);
+        assert_eq!(decoded.hiding, commitment.hiding);
+        assert_eq!(decoded.binding, commitment.binding);
+    }
+
+    #[test]
+    fn signature_share_round_trips_through_bytes() {
+        let msg = b"round trip this signature share";
+        let (shares, group_vk) = generate_threshold_fingerprint(2, 3).unwrap();
+        let signers = &shares[..2];
+
+        let (nonce_pairs, commitments): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|share| round_one(&mut OsRng, share))
+            .unzip();
+
+        let signature_share = round_two(&signers[0], &nonce_pairs[0], msg, &commitments, &group_vk);
+
+        let decoded = SignatureShare::from_bytes(&signature_share.to_bytes()).unwrap();
+
+        assert_eq!(decoded.index, signature_share.index);
+        assert_eq!(decoded.z, signature_share.z);
+    }
+
+    #[test]
+    fn malformed_share_bytes_are_rejected() {
+        let bytes = [0xFFu8; SHARE_LEN];
+        assert!(Share::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+mod certificate;
+mod components;
+mod decrypt;
+mod dh;
+mod encrypt;
+mod error;
+mod fingerprint;
+mod frost;
+mod password;
+mod proxy;
+mod seed;
+mod stream;
+mod verify_batch;
+
+pub use certificate::{export_certificate, verifying_key_from_certificate, CertificateError};
+pub use components::{extract_components, extract_components_mut, Components};
+pub use decrypt::{decrypt, Decrypt};
+pub use dh::{generate_dh_keys, DhKey, DhPublicKey};
+pub use encrypt::{encrypt, Encrypt};
+pub use error::Error;
+pub use fingerprint::{generate_fingerprint, sign, verify, Fingerprint, Signature};
+pub use frost::{
+    aggregate, generate_threshold_fingerprint, round_one, round_two, Commitment, NoncePair,
+    Share, SignatureShare, ThresholdError, COMMITMENT_LEN, SHARE_LEN, SIGNATURE_SHARE_LEN,
+};
+pub use password::{
+    derive_password_key, encrypt_password_setup, PasswordHeader, PasswordParams,
+};
+pub use proxy::{
+    generate_proxy_keypair, generate_transform_key, mask_content_key, transform,
+    unmask_content_key, MaskedContentKey, ProxyKey, ProxyPublicKey, TransformKey,
+};
+pub use seed::{dh_keys_from_seed, fingerprint_from_seed, fingerprint_with_prefix};
+pub use stream::{
+    decrypt_stream_init, encrypt_stream, DecryptStream, EncryptStream, StreamDecrypt,
+    StreamDecryptor, StreamEncryptor, SEGMENT_LEN,
+};
+pub use verify_batch::verify_batch;
@@ -0,0 +1,168 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! Password-based key derivation for [`crate::Encrypt::Password`]/[`crate::Decrypt::Password`],
+//! deriving the 256-bit content key from a passphrase with Argon2id so callers don't have to
+//! bolt a KDF onto the existing `encrypt`/`decrypt` pipeline themselves.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand_core::{CryptoRng, RngCore};
+
+const SALT_LEN: usize = 16;
+
+/// Serialized size of a [`PasswordHeader`], for callers threading it through the content
+/// header alongside the other modes.
+pub(crate) const HEADER_LEN: usize = SALT_LEN + 12;
+
+/// Argon2id cost parameters, serialized alongside the salt in the content header so
+/// [`crate::extract_components`] can surface them for decryption.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PasswordParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for PasswordParams {
+    /// ~64 MiB memory, 3 passes, single-lane — sane defaults for interactive use.
+    fn default() -> Self {
+        PasswordParams {
+            m_cost: 64 * 1024,
+            t_cost: 3,
+            p_cost: 1,
+        }
+    }
+}
+
+/// Header bytes encoding the salt and cost parameters, prepended to password-encrypted
+/// content so the same information used at encrypt time can be recovered at decrypt time.
+pub struct PasswordHeader {
+    pub salt: [u8; SALT_LEN],
+    pub params: PasswordParams,
+}
+
+impl PasswordHeader {
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+
+        bytes[..SALT_LEN].copy_from_slice(&self.salt);
+        bytes[SALT_LEN..SALT_LEN + 4].copy_from_slice(&self.params.m_cost.to_le_bytes());
+        bytes[SALT_LEN + 4..SALT_LEN + 8].copy_from_slice(&self.params.t_cost.to_le_bytes());
+        bytes[SALT_LEN + 8..].copy_from_slice(&self.params.p_cost.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; HEADER_LEN]) -> PasswordHeader {
+        PasswordHeader {
+            salt: bytes[..SALT_LEN].try_into().unwrap(),
+            params: PasswordParams {
+                m_cost: u32::from_le_bytes(bytes[SALT_LEN..SALT_LEN + 4].try_into().unwrap()),
+                t_cost: u32::from_le_bytes(
+                    bytes[SALT_LEN + 4..SALT_LEN + 8].try_into().unwrap(),
+                ),
+                p_cost: u32::from_le_bytes(bytes[SALT_LEN + 8..].try_into().unwrap()),
+            },
+        }
+    }
+}
+
+/// Upper bounds on Argon2id cost parameters accepted by [`derive_password_key`]. `params` may
+/// come straight off the wire from an untrusted [`PasswordHeader`] via
+/// [`crate::Decrypt::Password`], and `argon2::Params::new` happily accepts `m_cost`/`t_cost`
+/// up to `u32::MAX` — so without a cap, a crafted ciphertext could force a decryptor into a
+/// terabyte-scale, unbounded-time Argon2id run before the AEAD tag is ever checked. These are
+/// generous enough for any legitimate interactive or server-side use.
+const MAX_M_COST: u32 = 256 * 1024; // 256 MiB
+const MAX_T_COST: u32 = 10;
+const MAX_P_COST: u32 = 4;
+
+fn clamped_params(params: &PasswordParams) -> PasswordParams {
+    PasswordParams {
+        m_cost: params.m_cost.min(MAX_M_COST),
+        t_cost: params.t_cost.min(MAX_T_COST),
+        p_cost: params.p_cost.min(MAX_P_COST),
+    }
+}
+
+/// Derives a 256-bit content key from `passphrase` with Argon2id under `salt`/`params`,
+/// clamping `params` to sane maximums first.
+pub fn derive_password_key(
+    passphrase: &[u8],
+    salt: &[u8; SALT_LEN],
+    params: &PasswordParams,
+) -> Result<[u8; 32], argon2::Error> {
+    let params = clamped_params(params);
+    let argon2_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|_| argon2::Error::AlgorithmInvalid)?;
+
+    Ok(key)
+}
+
+/// Generates a random salt and derives the content key for a fresh `Encrypt::Password`
+/// operation, returning the key alongside the header to prepend to the encrypted content.
+pub fn encrypt_password_setup<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    passphrase: &[u8],
+    params: PasswordParams,
+) -> Result<([u8; 32], PasswordHeader), argon2::Error> {
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill_bytes(&mut salt);
+
+    let key = derive_password_key(passphrase, &salt, &params)?;
+
+    Ok((key, PasswordHeader { salt, params }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn header_round_trips_through_bytes() {
+        let header = PasswordHeader {
+            salt: [7u8; SALT_LEN],
+            params: PasswordParams::default(),
+        };
+
+        let decoded = PasswordHeader::from_bytes(&header.to_bytes());
+
+        assert_eq!(decoded.salt, header.salt);
+        assert_eq!(decoded.params, header.params);
+    }
+
+    #[test]
+    fn same_passphrase_and_header_derive_the_same_key() {
+        let (key, header) =
+            encrypt_password_setup(&mut OsRng, b"hunter2", PasswordParams::default()).unwrap();
+
+        let rederived = derive_password_key(b"hunter2", &header.salt, &header.params).unwrap();
+
+        assert_eq!(key, rederived);
+    }
+
+    #[test]
+    fn oversized_params_are_clamped_to_sane_maximums() {
+        let attacker_controlled = PasswordParams {
+            m_cost: u32::MAX,
+            t_cost: u32::MAX,
+            p_cost: u32::MAX,
+        };
+
+        let clamped = clamped_params(&attacker_controlled);
+
+        assert_eq!(clamped.m_cost, MAX_M_COST);
+        assert_eq!(clamped.t_cost, MAX_T_COST);
+        assert_eq!(clamped.p_cost, MAX_P_COST);
+    }
+}
@@ -0,0 +1,175 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! Proxy re-encryption for content keys: lets a semi-trusted proxy re-wrap an already-masked
+//! content key for a new recipient without ever learning the content key itself.
+//!
+//! Modeled as ElGamal-style masking over the ed25519 scalar field: the content key is masked
+//! as `(c1 = r·B, c2 = K + r·delegator_pub)`, and a transform key lets the proxy rescale `c1`
+//! in place so the delegatee's own private key peels off the mask exactly as the delegator's
+//! would have.
+//!
+//! **Scope note, read before wiring this up**: a proxy/delegator granting access with only the
+//! delegatee's *public* key (`generate_transform_key(delegator_priv, delegatee_pub)`, the
+//! non-interactive form this module was originally asked for) needs a transform key that can
+//! rescale a point (`c1`) using only a public key as input. On a bilinear-pairing curve that's
+//! exactly what the pairing operation gives you; Curve25519 has no such pairing, so no
+//! non-interactive, public-key-only transform key exists here. What's shipped instead is the
+//! symmetric/interactive variant — `rk = delegator_priv / delegatee_priv` — which needs the
+//! delegatee's own private scalar to construct. That's a meaningfully different (weaker) trust
+//! model than the one requested: it only make sense when the delegator and delegatee already
+//! share enough trust to exchange private material once, e.g. both are controlled by the same
+//! operator during a key-rotation handoff. A fully general, public-key-only multi-hop scheme
+//! would require moving to a pairing-friendly curve (e.g. BLS12-381), which is out of scope
+//! for this module.
+//!
+//! **Why this doesn't plug into [`crate::Encrypt::Dh`]**: that flow's `DhKey`/`DhPublicKey` are
+//! x25519 (Montgomery-form) keys, whose group law only supports scalar multiplication — there's
+//! no defined point-addition for Montgomery u-coordinates, which the `c2 = K + r·pub` masking
+//! step here requires. Recovering the information to switch to the complete
+//! twisted-Edwards addition law needs the sign bit of the corresponding Edwards
+//! y-coordinate, which an x25519 public key doesn't carry. So this module can't mask a key
+//! under a real `DhPublicKey`; it defines and generates its own [`ProxyKey`]/[`ProxyPublicKey`]
+//! pair instead, the same way FROST keeps its own scalar/point types separate from the x25519
+//! DH material.
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, edwards::EdwardsPoint, scalar::Scalar};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// A private key for this module's masking scheme. Distinct from [`crate::DhKey`]: see the
+/// module docs for why the two aren't interchangeable.
+#[derive(Clone, Copy)]
+pub struct ProxyKey(Scalar);
+
+/// The public half of a [`ProxyKey`].
+#[derive(Clone, Copy)]
+pub struct ProxyPublicKey(EdwardsPoint);
+
+/// Generates a fresh, random key pair for masking/unmasking content keys with this module.
+pub fn generate_proxy_keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (ProxyKey, ProxyPublicKey) {
+    let priv_key = random_scalar(rng);
+    let pub_key = &priv_key * ED25519_BASEPOINT_TABLE;
+
+    (ProxyKey(priv_key), ProxyPublicKey(pub_key))
+}
+
+/// A masked content key, as produced by [`mask_content_key`] or rewritten by [`transform`].
+#[derive(Clone, Copy)]
+pub struct MaskedContentKey {
+    c1: EdwardsPoint,
+    c2: EdwardsPoint,
+}
+
+/// A one-way rewriting key handed to the proxy, produced by [`generate_transform_key`].
+pub struct TransformKey(Scalar);
+
+fn point_to_content_key(point: &EdwardsPoint) -> [u8; 32] {
+    let mut hasher = Sha512::new();
+    hasher.update(b"RGP-PRE-content-key");
+    hasher.update(point.compress().as_bytes());
+
+    let digest = hasher.finalize();
+    digest[..32].try_into().unwrap()
+}
+
+/// Generates a fresh content key and masks it under `delegator_pub` so that only the
+/// delegator (or a delegatee the proxy has [`transform`]ed it for) can recover it.
+pub fn mask_content_key<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    delegator_pub: ProxyPublicKey,
+) -> ([u8; 32], MaskedContentKey) {
+    let k = random_scalar(rng);
+    let r = random_scalar(rng);
+
+    let k_point = &k * ED25519_BASEPOINT_TABLE;
+    let content_key = point_to_content_key(&k_point);
+
+    let masked = MaskedContentKey {
+        c1: &r * ED25519_BASEPOINT_TABLE,
+        c2: k_point + r * delegator_pub.0,
+    };
+
+    (content_key, masked)
+}
+
+/// Builds the transform key a delegator hands to the proxy to authorize re-wrapping for
+/// `delegatee_priv`'s holder, computed as `rk = delegator_priv / delegatee_priv`.
+///
+/// This takes the delegatee's *private* key, not their public key — see the module docs for
+/// why a public-key-only variant isn't possible on this curve.
+pub fn generate_transform_key(delegator_priv: ProxyKey, delegatee_priv: ProxyKey) -> TransformKey {
+    TransformKey(delegator_priv.0 * delegatee_priv.0.invert())
+}
+
+/// Rewrites `masked` so the delegatee can recover the content key with their own private
+/// key, without the proxy learning the key or either party's private scalar. Rescales the
+/// existing `c1` by the transform key's scalar rather than replacing it, preserving the
+/// per-message randomness `r` used in [`mask_content_key`].
+pub fn transform(masked: &MaskedContentKey, transform_key: &TransformKey) -> MaskedContentKey {
+    MaskedContentKey {
+        c1: transform_key.0 * masked.c1,
+        c2: masked.c2,
+    }
+}
+
+/// Recovers the content key from a [`MaskedContentKey`] using the holder's private key,
+/// mirroring [`crate::Decrypt::Dh`]'s unwrap step.
+pub fn unmask_content_key(receiver_priv: ProxyKey, masked: &MaskedContentKey) -> [u8; 32] {
+    let k_point = masked.c2 - receiver_priv.0 * masked.c1;
+    point_to_content_key(&k_point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn delegatee_recovers_content_key_after_transform() {
+        let mut rng = OsRng;
+
+        let (delegator_priv, delegator_pub) = generate_proxy_keypair(&mut rng);
+        let (delegatee_priv, _) = generate_proxy_keypair(&mut rng);
+
+        let (content_key, masked) = mask_content_key(&mut rng, delegator_pub);
+
+        let transform_key = generate_transform_key(delegator_priv, delegatee_priv);
+        let rewrapped = transform(&masked, &transform_key);
+
+        assert_eq!(unmask_content_key(delegatee_priv, &rewrapped), content_key);
+    }
+
+    #[test]
+    fn delegator_still_recovers_content_key_untransformed() {
+        let mut rng = OsRng;
+
+        let (delegator_priv, delegator_pub) = generate_proxy_keypair(&mut rng);
+
+        let (content_key, masked) = mask_content_key(&mut rng, delegator_pub);
+
+        assert_eq!(unmask_content_key(delegator_priv, &masked), content_key);
+    }
+
+    #[test]
+    fn unrelated_key_does_not_recover_content_key() {
+        let mut rng = OsRng;
+
+        let (_, delegator_pub) = generate_proxy_keypair(&mut rng);
+        let (unrelated_priv, _) = generate_proxy_keypair(&mut rng);
+
+        let (content_key, masked) = mask_content_key(&mut rng, delegator_pub);
+
+        assert_ne!(unmask_content_key(unrelated_priv, &masked), content_key);
+    }
+}
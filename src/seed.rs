@@ -0,0 +1,118 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! Deterministic ("brain key") derivation of fingerprints and DH keys from a passphrase, for
+//! backup/restore and reproducible identities across devices. [`crate::generate_fingerprint`]
+//! and [`crate::generate_dh_keys`] are always random; the functions here stretch a passphrase
+//! through scrypt into 32 bytes of seed material and derive the same key types from it.
+
+use crate::Fingerprint;
+use ed25519_dalek::VerifyingKey;
+use scrypt::{scrypt, Params};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+fn stretch(passphrase: &[u8], salt: &[u8]) -> [u8; 32] {
+    let params = Params::new(15, 8, 1, 32).expect("fixed scrypt params are always valid");
+
+    let mut seed = [0u8; 32];
+    scrypt(passphrase, salt, &params, &mut seed).expect("output length matches Params");
+
+    seed
+}
+
+/// Deterministically derives a signing fingerprint from `passphrase`/`salt`, stretching the
+/// passphrase through scrypt and using the resulting 32 bytes as the ed25519 seed.
+pub fn fingerprint_from_seed(passphrase: &[u8], salt: &[u8]) -> (Fingerprint, VerifyingKey) {
+    let seed = stretch(passphrase, salt);
+    let fingerprint = Fingerprint::from_bytes(seed);
+    let verifying_key = fingerprint.verifying_key();
+
+    (fingerprint, verifying_key)
+}
+
+/// Deterministically derives an x25519 key pair from `passphrase`/`salt`, the DH analogue of
+/// [`fingerprint_from_seed`].
+pub fn dh_keys_from_seed(passphrase: &[u8], salt: &[u8]) -> (StaticSecret, PublicKey) {
+    let seed = stretch(passphrase, salt);
+    let secret = StaticSecret::from(seed);
+    let public = PublicKey::from(&secret);
+
+    (secret, public)
+}
+
+/// Repeatedly derives a fingerprint from `passphrase`, varying the salt by appending an
+/// incrementing counter, until the verifying key's encoded bytes start with `prefix` or
+/// `max_attempts` searches have been made. Mirrors vanity-key mining; the expected number of
+/// attempts grows exponentially with `prefix.len()`, so callers should keep prefixes short
+/// and bound `max_attempts` accordingly.
+///
+/// Returns `None` if `prefix` is longer than a verifying key (32 bytes) or no match was found
+/// within `max_attempts`.
+pub fn fingerprint_with_prefix(
+    passphrase: &[u8],
+    base_salt: &[u8],
+    prefix: &[u8],
+    max_attempts: u64,
+) -> Option<(Vec<u8>, Fingerprint, VerifyingKey)> {
+    if prefix.len() > 32 {
+        return None;
+    }
+
+    for counter in 0..max_attempts {
+        let mut salt = base_salt.to_vec();
+        salt.extend_from_slice(&counter.to_le_bytes());
+
+        let (fingerprint, verifying_key) = fingerprint_from_seed(passphrase, &salt);
+
+        if verifying_key.as_bytes().starts_with(prefix) {
+            return Some((salt, fingerprint, verifying_key));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_passphrase_and_salt_derive_the_same_fingerprint() {
+        let (_, first) = fingerprint_from_seed(b"correct horse battery staple", b"salt");
+        let (_, second) = fingerprint_from_seed(b"correct horse battery staple", b"salt");
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_salts_derive_different_fingerprints() {
+        let (_, first) = fingerprint_from_seed(b"correct horse battery staple", b"salt-a");
+        let (_, second) = fingerprint_from_seed(b"correct horse battery staple", b"salt-b");
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn seed_derived_fingerprint_signs_and_verifies_through_the_crate_api() {
+        let (fingerprint, verifying_key) =
+            fingerprint_from_seed(b"correct horse battery staple", b"salt");
+
+        let signature = crate::sign(&fingerprint, b"hello");
+
+        assert!(crate::verify(&signature, &verifying_key, b"hello").is_ok());
+    }
+
+    #[test]
+    fn prefix_longer_than_a_verifying_key_is_rejected() {
+        assert!(fingerprint_with_prefix(b"pass", b"salt", &[0u8; 33], 10).is_none());
+    }
+
+    #[test]
+    fn exhausting_max_attempts_without_a_match_returns_none() {
+        assert!(fingerprint_with_prefix(b"pass", b"salt", &[0xAB, 0xCD], 4).is_none());
+    }
+}
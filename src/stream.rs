@@ -0,0 +1,502 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! Streaming counterpart to [`crate::encrypt`]/[`crate::decrypt`] for payloads too large to
+//! buffer in memory. [`StreamEncryptor`]/[`StreamDecryptor`] are the low-level segmented-AEAD
+//! primitive: content is split into fixed-size segments, each encrypted under a per-segment
+//! nonce built from a 32-bit counter and a 1-bit "final" flag, so truncation and reordering
+//! are rejected by the AEAD tag itself.
+//!
+//! [`encrypt_stream`]/[`decrypt_stream_init`] build on that primitive the way
+//! [`crate::encrypt`]/[`crate::decrypt`] wrap a one-shot cipher: they resolve the content key
+//! from [`crate::Encrypt`]/[`StreamDecrypt`] exactly like the one-shot API and produce a
+//! detached ed25519 signature, so callers don't have to manage a content key or drive the
+//! low-level primitive directly. The wire format necessarily differs from
+//! [`crate::encrypt`]'s in two ways, both because the full ciphertext is never buffered: an
+//! [`crate::Encrypt::Hmac`] tag can't be computed until every segment is sealed, so it's
+//! appended after the final segment instead of living in the header; and the final signature
+//! covers a running SHA-512 digest of the sealed segments rather than the ciphertext itself.
+
+use crate::encrypt::{wrap_key_for_recipient, WRAPPED_KEY_LEN};
+use crate::password::{derive_password_key, PasswordHeader, HEADER_LEN as PASSWORD_HEADER_LEN};
+use crate::{decrypt::unwrap_key, DhKey, DhPublicKey, Encrypt, Error, Fingerprint, Signature};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use ed25519_dalek::VerifyingKey;
+use hmac::{Hmac, Mac};
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+
+/// Plaintext segment size; callers may feed `update` with chunks of any size, they're
+/// re-buffered internally to this boundary.
+pub const SEGMENT_LEN: usize = 64 * 1024;
+
+const NONCE_PREFIX_LEN: usize = 7;
+
+fn segment_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, is_final: bool) -> Nonce {
+    let mut bytes = [0u8; 12];
+    bytes[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    bytes[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_le_bytes()[..4]);
+    bytes[11] = is_final as u8;
+
+    Nonce::clone_from_slice(&bytes)
+}
+
+/// Low-level segmented cipher: encrypts content incrementally, segment by segment, under a
+/// caller-supplied content key. Most callers want [`encrypt_stream`] instead.
+pub struct StreamEncryptor {
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl StreamEncryptor {
+    /// Starts a new stream under `content_key`, returning the header to prepend to the
+    /// ciphertext (the nonce prefix) alongside the encryptor.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R, content_key: &[u8; 32]) -> (Vec<u8>, StreamEncryptor) {
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        rng.fill_bytes(&mut nonce_prefix);
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(content_key));
+
+        (
+            nonce_prefix.to_vec(),
+            StreamEncryptor {
+                cipher,
+                nonce_prefix,
+                counter: 0,
+                buffer: Vec::with_capacity(SEGMENT_LEN),
+            },
+        )
+    }
+
+    fn seal_segment(&mut self, plaintext: &[u8], is_final: bool) -> Vec<u8> {
+        let nonce = segment_nonce(&self.nonce_prefix, self.counter, is_final);
+        self.counter += 1;
+
+        self.cipher
+            .encrypt(&nonce, plaintext)
+            .expect("chacha20poly1305 encryption is infallible for valid keys/nonces")
+    }
+
+    /// Buffers `chunk` and emits ciphertext for every full segment it completes. The
+    /// returned `Vec` may be empty if `chunk` didn't fill a segment yet.
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut out = Vec::new();
+
+        while self.buffer.len() >= SEGMENT_LEN {
+            let segment: Vec<u8> = self.buffer.drain(..SEGMENT_LEN).collect();
+            out.extend(self.seal_segment(&segment, false));
+        }
+
+        out
+    }
+
+    /// Flushes any buffered plaintext plus `last_chunk` as the final segment, sealing it
+    /// with the final-flag nonce bit set.
+    pub fn finalize(mut self, last_chunk: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(last_chunk);
+        self.seal_segment(&self.buffer.clone(), true)
+    }
+}
+
+/// Low-level segmented cipher: decrypts a stream produced by [`StreamEncryptor`], segment by
+/// segment, under a caller-supplied content key. Most callers want [`decrypt_stream_init`]
+/// instead.
+pub struct StreamDecryptor {
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+}
+
+impl StreamDecryptor {
+    /// Resumes a stream under `content_key` from the header emitted by
+    /// [`StreamEncryptor::new`].
+    pub fn new(header: &[u8], content_key: &[u8; 32]) -> Result<StreamDecryptor, Error> {
+        let nonce_prefix: [u8; NONCE_PREFIX_LEN] = header.try_into().map_err(|_| Error::Malformed)?;
+
+        Ok(StreamDecryptor {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(content_key)),
+            nonce_prefix,
+            counter: 0,
+        })
+    }
+
+    /// Decrypts one non-final segment. The counter bound into the nonce means segments fed
+    /// out of order, duplicated, or dropped fail to authenticate.
+    pub fn update(&mut self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = segment_nonce(&self.nonce_prefix, self.counter, false);
+        self.counter += 1;
+
+        self.cipher.decrypt(&nonce, segment).map_err(|_| Error::Decrypt)
+    }
+
+    /// Decrypts the final segment; fails if `segment` wasn't sealed with the final-flag bit
+    /// set, rejecting truncated streams that drop the last segment.
+    pub fn finalize(self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = segment_nonce(&self.nonce_prefix, self.counter, true);
+
+        self.cipher.decrypt(&nonce, segment).map_err(|_| Error::Decrypt)
+    }
+}
+
+/// High-level streaming encryptor produced by [`encrypt_stream`], wrapping [`StreamEncryptor`]
+/// with the running state needed to finish the job like [`crate::encrypt`] does: an optional
+/// HMAC-SHA256 tag for [`crate::Encrypt::Hmac`], and a SHA-512 digest of every sealed segment
+/// to sign at [`EncryptStream::finalize`].
+pub struct EncryptStream {
+    fingerprint: Fingerprint,
+    encryptor: StreamEncryptor,
+    hasher: Sha512,
+    hmac: Option<Hmac<Sha256>>,
+}
+
+/// Starts a stream under `fingerprint`, resolving the content key from `mode` the same way
+/// [`crate::encrypt`] does, and returns the header to prepend to the ciphertext (mode tag,
+/// mode-specific header, and nonce prefix) alongside an [`EncryptStream`].
+pub fn encrypt_stream(fingerprint: Fingerprint, mode: Encrypt) -> Result<(Vec<u8>, EncryptStream), Error> {
+    let mut header = Vec::new();
+    let mut hmac = None;
+
+    let content_key: [u8; 32] = match mode {
+        Encrypt::Session(key) => {
+            header.push(0u8);
+            key.0
+        }
+        Encrypt::Hmac(hmac_key, content_key, counter) => {
+            header.push(1u8);
+            header.extend_from_slice(&counter.to_le_bytes());
+
+            hmac = Some(
+                <Hmac<Sha256> as Mac>::new_from_slice(&hmac_key.0)
+                    .expect("HMAC-SHA256 accepts any key length"),
+            );
+
+            content_key.0
+        }
+        Encrypt::Dh(sender_priv, recipients) => {
+            header.push(2u8);
+            header.extend_from_slice(&(recipients.len() as u16).to_le_bytes());
+
+            let mut content_key = [0u8; 32];
+            OsRng.fill_bytes(&mut content_key);
+
+            for recipient_pub in recipients {
+                let wrapped = wrap_key_for_recipient(sender_priv, *recipient_pub, &content_key);
+                header.extend_from_slice(&wrapped);
+            }
+
+            content_key
+        }
+        Encrypt::Password(passphrase, params) => {
+            let (content_key, password_header) =
+                crate::password::encrypt_password_setup(&mut OsRng, passphrase, params)
+                    .map_err(|_| Error::Encrypt)?;
+
+            header.push(3u8);
+            header.extend_from_slice(&password_header.to_bytes());
+
+            content_key
+        }
+    };
+
+    let (nonce_prefix, encryptor) = StreamEncryptor::new(&mut OsRng, &content_key);
+    header.extend_from_slice(&nonce_prefix);
+
+    Ok((
+        header,
+        EncryptStream {
+            fingerprint,
+            encryptor,
+            hasher: Sha512::new(),
+            hmac,
+        },
+    ))
+}
+
+impl EncryptStream {
+    /// Buffers `chunk` and emits ciphertext for every full segment it completes, same as
+    /// [`StreamEncryptor::update`].
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let sealed = self.encryptor.update(chunk);
+
+        self.hasher.update(&sealed);
+        if let Some(hmac) = &mut self.hmac {
+            hmac.update(&sealed);
+        }
+
+        sealed
+    }
+
+    /// Seals `last_chunk` as the final segment, then appends the HMAC tag (for
+    /// [`crate::Encrypt::Hmac`]) and the detached ed25519 signature over the digest of every
+    /// sealed segment.
+    pub fn finalize(mut self, last_chunk: &[u8]) -> Vec<u8> {
+        let sealed = self.encryptor.finalize(last_chunk);
+        self.hasher.update(&sealed);
+
+        let mut out = sealed;
+
+        if let Some(mut hmac) = self.hmac {
+            hmac.update(&out);
+            out.extend_from_slice(&hmac.finalize().into_bytes());
+        }
+
+        let digest = self.hasher.finalize();
+        let signature = crate::sign(&self.fingerprint, &digest);
+        out.extend_from_slice(&signature.to_bytes());
+
+        out
+    }
+}
+
+/// Mirrors [`crate::Decrypt`] for resolving a stream's content key from the header produced by
+/// [`encrypt_stream`].
+pub enum StreamDecrypt<'a> {
+    Session(DhKey),
+    Hmac(DhKey, DhPublicKey),
+    /// The sender's public key and this recipient's own private key; the wrapped key itself
+    /// is read from the header by [`decrypt_stream_init`] using `index`.
+    Dh(DhPublicKey, DhKey),
+    Password(&'a [u8]),
+}
+
+/// High-level streaming decryptor produced by [`decrypt_stream_init`].
+pub struct DecryptStream {
+    decryptor: StreamDecryptor,
+    hasher: Sha512,
+    hmac: Option<Hmac<Sha256>>,
+}
+
+/// Parses the header from [`encrypt_stream`], resolves the content key via `mode`, and returns
+/// a [`DecryptStream`] ready for [`DecryptStream::update`]/[`DecryptStream::finalize`].
+///
+/// `index` selects which recipient's wrapped key to read out of the header for
+/// [`StreamDecrypt::Dh`]; ignored for every other mode.
+pub fn decrypt_stream_init(header: &[u8], index: usize, mode: StreamDecrypt) -> Result<DecryptStream, Error> {
+    let (&tag, mut rest) = header.split_first().ok_or(Error::Malformed)?;
+
+    let (content_key, hmac) = match (tag, mode) {
+        (0, StreamDecrypt::Session(key)) => (key.0, None),
+        (1, StreamDecrypt::Hmac(hmac_key, content_key)) => {
+            if rest.len() < 4 {
+                return Err(Error::Malformed);
+            }
+            rest = &rest[4..];
+
+            let hmac = <Hmac<Sha256> as Mac>::new_from_slice(&hmac_key.0)
+                .expect("HMAC-SHA256 accepts any key length");
+
+            (content_key.0, Some(hmac))
+        }
+        (2, StreamDecrypt::Dh(sender_pub, receiver_priv)) => {
+            if rest.len() < 2 {
+                return Err(Error::Malformed);
+            }
+            let count = u16::from_le_bytes(rest[..2].try_into().unwrap()) as usize;
+            rest = &rest[2..];
+
+            if rest.len() < count * WRAPPED_KEY_LEN {
+                return Err(Error::Malformed);
+            }
+
+            let wrapped: [u8; WRAPPED_KEY_LEN] = rest
+                .get(index * WRAPPED_KEY_LEN..(index + 1) * WRAPPED_KEY_LEN)
+                .ok_or(Error::Malformed)?
+                .try_into()
+                .unwrap();
+
+            rest = &rest[count * WRAPPED_KEY_LEN..];
+
+            (unwrap_key(&wrapped, sender_pub, receiver_priv)?, None)
+        }
+        (3, StreamDecrypt::Password(passphrase)) => {
+            if rest.len() < PASSWORD_HEADER_LEN {
+                return Err(Error::Malformed);
+            }
+            let header_bytes: [u8; PASSWORD_HEADER_LEN] =
+                rest[..PASSWORD_HEADER_LEN].try_into().unwrap();
+            let password_header = PasswordHeader::from_bytes(&header_bytes);
+            rest = &rest[PASSWORD_HEADER_LEN..];
+
+            let content_key = derive_password_key(passphrase, &password_header.salt, &password_header.params)
+                .map_err(|_| Error::Decrypt)?;
+
+            (content_key, None)
+        }
+        _ => return Err(Error::Malformed),
+    };
+
+    let nonce_prefix: [u8; NONCE_PREFIX_LEN] = rest.try_into().map_err(|_| Error::Malformed)?;
+
+    Ok(DecryptStream {
+        decryptor: StreamDecryptor {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&content_key)),
+            nonce_prefix,
+            counter: 0,
+        },
+        hasher: Sha512::new(),
+        hmac,
+    })
+}
+
+impl DecryptStream {
+    /// Decrypts one non-final segment, same as [`StreamDecryptor::update`].
+    pub fn update(&mut self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        self.hasher.update(segment);
+        if let Some(hmac) = &mut self.hmac {
+            hmac.update(segment);
+        }
+
+        self.decryptor.update(segment)
+    }
+
+    /// Decrypts the final segment, checks the trailing HMAC tag (for
+    /// [`crate::Encrypt::Hmac`]), and, if `verifying_key` is given, checks the detached
+    /// ed25519 signature over the digest of every sealed segment.
+    pub fn finalize(
+        mut self,
+        segment_and_trailer: &[u8],
+        verifying_key: Option<&VerifyingKey>,
+    ) -> Result<Vec<u8>, Error> {
+        let trailer_len = 64 + if self.hmac.is_some() { 32 } else { 0 };
+
+        if segment_and_trailer.len() < trailer_len {
+            return Err(Error::Malformed);
+        }
+
+        let (segment, trailer) = segment_and_trailer.split_at(segment_and_trailer.len() - trailer_len);
+        let (tag, signature_bytes) = trailer.split_at(trailer.len() - 64);
+
+        self.hasher.update(segment);
+        if let Some(hmac) = &mut self.hmac {
+            hmac.update(segment);
+        }
+
+        let plaintext = self.decryptor.finalize(segment)?;
+
+        if let Some(hmac) = self.hmac {
+            hmac.verify_slice(tag).map_err(|_| Error::Decrypt)?;
+        }
+
+        if let Some(verifying_key) = verifying_key {
+            let digest = self.hasher.finalize();
+            let signature = Signature::from_bytes(signature_bytes.try_into().unwrap());
+            crate::verify(&signature, verifying_key, &digest)?;
+        }
+
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_dh_keys, generate_fingerprint};
+
+    #[test]
+    fn stream_round_trips_across_multiple_segments() {
+        let mut content_key = [0u8; 32];
+        OsRng.fill_bytes(&mut content_key);
+
+        let (header, mut encryptor) = StreamEncryptor::new(&mut OsRng, &content_key);
+
+        let first_segment = vec![1u8; SEGMENT_LEN];
+        let mut ciphertext = encryptor.update(&first_segment);
+        ciphertext.extend(encryptor.finalize(&[2u8; 128]));
+
+        let mut decryptor = StreamDecryptor::new(&header, &content_key).unwrap();
+
+        let first_ciphertext = &ciphertext[..ciphertext.len() - (128 + 16)];
+        let final_ciphertext = &ciphertext[ciphertext.len() - (128 + 16)..];
+
+        let decrypted_first = decryptor.update(first_ciphertext).unwrap();
+        let decrypted_final = decryptor.finalize(final_ciphertext).unwrap();
+
+        assert_eq!(decrypted_first, first_segment);
+        assert_eq!(decrypted_final, vec![2u8; 128]);
+    }
+
+    #[test]
+    fn encrypt_stream_session_round_trips_and_verifies() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (key, _) = generate_dh_keys();
+
+        let (header, mut encryptor) = encrypt_stream(fingerprint, Encrypt::Session(key)).unwrap();
+
+        let first_segment = vec![7u8; SEGMENT_LEN];
+        let mut ciphertext = encryptor.update(&first_segment);
+        ciphertext.extend(encryptor.finalize(b"tail"));
+
+        let mut decryptor =
+            decrypt_stream_init(&header, 0, StreamDecrypt::Session(key)).unwrap();
+
+        let final_len = b"tail".len() + 16 + 64;
+        let first_ciphertext = &ciphertext[..ciphertext.len() - final_len];
+        let final_ciphertext = &ciphertext[ciphertext.len() - final_len..];
+
+        let decrypted_first = decryptor.update(first_ciphertext).unwrap();
+        let decrypted_final = decryptor
+            .finalize(final_ciphertext, Some(&verifying_key))
+            .unwrap();
+
+        assert_eq!(decrypted_first, first_segment);
+        assert_eq!(decrypted_final, b"tail");
+    }
+
+    #[test]
+    fn encrypt_stream_dh_round_trips_for_recipient() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (sender_priv, sender_pub) = generate_dh_keys();
+        let (receiver_priv, receiver_pub) = generate_dh_keys();
+
+        let (header, encryptor) =
+            encrypt_stream(fingerprint, Encrypt::Dh(sender_priv, &[receiver_pub])).unwrap();
+
+        let ciphertext = encryptor.finalize(b"single segment");
+
+        let decryptor =
+            decrypt_stream_init(&header, 0, StreamDecrypt::Dh(sender_pub, receiver_priv)).unwrap();
+
+        let decrypted = decryptor
+            .finalize(&ciphertext, Some(&verifying_key))
+            .unwrap();
+
+        assert_eq!(decrypted, b"single segment");
+    }
+
+    #[test]
+    fn encrypt_stream_hmac_tag_is_checked() {
+        let (fingerprint, verifying_key) = generate_fingerprint();
+        let (hmac_key, hmac_pub) = generate_dh_keys();
+
+        let (header, encryptor) =
+            encrypt_stream(fingerprint, Encrypt::Hmac(hmac_key, hmac_pub, 0)).unwrap();
+
+        let mut ciphertext = encryptor.finalize(b"hmac stream");
+
+        let decryptor =
+            decrypt_stream_init(&header, 0, StreamDecrypt::Hmac(hmac_key, hmac_pub)).unwrap();
+        assert!(decryptor
+            .finalize(&ciphertext, Some(&verifying_key))
+            .is_ok());
+
+        let tampered_index = ciphertext.len() - 64 - 1;
+        ciphertext[tampered_index] ^= 0xFF;
+
+        let decryptor =
+            decrypt_stream_init(&header, 0, StreamDecrypt::Hmac(hmac_key, hmac_pub)).unwrap();
+        assert!(decryptor
+            .finalize(&ciphertext, Some(&verifying_key))
+            .is_err());
+    }
+}
@@ -0,0 +1,146 @@
+/*
+Copyright (c) 2024 sean watters
+
+Licensed under the MIT license <LICENSE or https://opensource.org/licenses/MIT>.
+This file may not be copied, modified, or distributed except according to those terms.
+*/
+
+//! Batch verification for ed25519 signatures produced by [`crate::sign`], checking many at
+//! once with a single random-linear-combination equation instead of one scalar-mult per item.
+
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE, edwards::CompressedEdwardsY, scalar::Scalar,
+    traits::VartimeMultiscalarMul,
+};
+use ed25519_dalek::{Signature, VerifyingKey};
+use rand_core::RngCore;
+use sha2::{Digest, Sha512};
+
+fn challenge(r: &CompressedEdwardsY, a: &VerifyingKey, msg: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(r.as_bytes());
+    hasher.update(a.as_bytes());
+    hasher.update(msg);
+
+    Scalar::from_hash(hasher)
+}
+
+/// Verifies many `(signature, verifying_key, message)` triples at once using a
+/// random-linear-combination check: for independent 128-bit scalars `z_i`,
+/// `(Σ z_i·s_i)·B == Σ z_i·R_i + Σ (z_i·c_i)·A_i`.
+///
+/// On success, every signature in `items` is valid. On failure, falls back to verifying each
+/// item individually and returns the index of the first one that doesn't verify.
+pub fn verify_batch(items: &[(Signature, VerifyingKey, &[u8])]) -> Result<(), usize> {
+    let mut rng = rand_core::OsRng;
+
+    let mut r_points = Vec::with_capacity(items.len());
+    let mut a_points = Vec::with_capacity(items.len());
+    let mut z_scalars = Vec::with_capacity(items.len());
+    let mut s_scalars = Vec::with_capacity(items.len());
+    let mut c_scalars = Vec::with_capacity(items.len());
+
+    for (signature, verifying_key, msg) in items {
+        let bytes = signature.to_bytes();
+
+        let r = CompressedEdwardsY::from_slice(&bytes[..32])
+            .map_err(|_| ())
+            .and_then(|r| r.decompress().ok_or(()));
+        let s = Scalar::from_canonical_bytes(bytes[32..].try_into().unwrap());
+
+        let (Ok(r), Some(s)) = (r, Option::<Scalar>::from(s)) else {
+            return verify_individually(items);
+        };
+
+        let mut z_bytes = [0u8; 16];
+        rng.fill_bytes(&mut z_bytes);
+        let z = Scalar::from(u128::from_le_bytes(z_bytes));
+
+        let Some(a) = CompressedEdwardsY::from_slice(verifying_key.as_bytes())
+            .ok()
+            .and_then(|a| a.decompress())
+        else {
+            return verify_individually(items);
+        };
+
+        c_scalars.push(challenge(&r.compress(), verifying_key, msg));
+        r_points.push(r);
+        a_points.push(a);
+        z_scalars.push(z);
+        s_scalars.push(s);
+    }
+
+    let lhs: Scalar = z_scalars
+        .iter()
+        .zip(s_scalars.iter())
+        .map(|(z, s)| z * s)
+        .sum();
+    let lhs_point = &lhs * ED25519_BASEPOINT_TABLE;
+
+    let scalars = z_scalars
+        .iter()
+        .cloned()
+        .chain(z_scalars.iter().zip(c_scalars.iter()).map(|(z, c)| z * c));
+    let points = r_points.into_iter().chain(a_points);
+
+    let rhs_point = curve25519_dalek::edwards::EdwardsPoint::vartime_multiscalar_mul(scalars, points);
+
+    if lhs_point == rhs_point {
+        Ok(())
+    } else {
+        verify_individually(items)
+    }
+}
+
+fn verify_individually(items: &[(Signature, VerifyingKey, &[u8])]) -> Result<(), usize> {
+    for (index, (signature, verifying_key, msg)) in items.iter().enumerate() {
+        if crate::verify(signature, verifying_key, msg).is_err() {
+            return Err(index);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_batch_verifies() {
+        let (fingerprint_a, verifying_key_a) = crate::generate_fingerprint();
+        let (fingerprint_b, verifying_key_b) = crate::generate_fingerprint();
+
+        let msg_a = b"first message";
+        let msg_b = b"second message";
+
+        let signature_a = crate::sign(&fingerprint_a, msg_a);
+        let signature_b = crate::sign(&fingerprint_b, msg_b);
+
+        let items = [
+            (signature_a, verifying_key_a, msg_a.as_slice()),
+            (signature_b, verifying_key_b, msg_b.as_slice()),
+        ];
+
+        assert!(verify_batch(&items).is_ok());
+    }
+
+    #[test]
+    fn batch_with_a_bad_signature_reports_its_index() {
+        let (fingerprint_a, verifying_key_a) = crate::generate_fingerprint();
+        let (fingerprint_b, verifying_key_b) = crate::generate_fingerprint();
+
+        let msg_a = b"first message";
+        let msg_b = b"second message";
+
+        let signature_a = crate::sign(&fingerprint_a, msg_a);
+        let signature_b = crate::sign(&fingerprint_b, b"tampered");
+
+        let items = [
+            (signature_a, verifying_key_a, msg_a.as_slice()),
+            (signature_b, verifying_key_b, msg_b.as_slice()),
+        ];
+
+        assert_eq!(verify_batch(&items), Err(1));
+    }
+}